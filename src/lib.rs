@@ -1,6 +1,20 @@
+#![cfg_attr(not(test), no_std)]
 //! URL encoding/decoding functions
 //!
 //! Check [`encode`] and [`decode`] docs for example usage
+//!
+//! [`encode_into`]/[`decode_into`] and the [`EncodeIter`]/[`DecodeIter`]
+//! they're built on don't allocate and work without the `alloc` feature;
+//! the other functions return a `String`, `Vec<u8>` or `Cow` and need it
+//! (enabled by default).
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+pub mod punycode;
 
 /// Bitmask that contains allowed character set
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -61,7 +75,114 @@ impl Bitmask {
 }
 
 const HEX: &[u8] = b"0123456789ABCDEF";
-/// Encodes given slice using provided [`Bitmask`]
+
+/// Controls how the space character is handled by [`encode_with`]/[`decode_with`]
+///
+/// RFC 3986 percent-encoding has no special case for space: it is just
+/// another reserved byte that becomes `%20`, and `+` is a literal `+`.
+/// `application/x-www-form-urlencoded`, the format HTML forms actually use,
+/// instead maps space to `+`. Mixing the two up mangles either literal `+`
+/// characters or spaces, so callers need to pick one explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceMode {
+    /// RFC 3986 semantics: space becomes `%20`; `+` is left untouched. Pairs
+    /// naturally with [`Bitmask::RFC3986`].
+    Percent,
+    /// `application/x-www-form-urlencoded` semantics: space becomes `+` and
+    /// `+` decodes back to space. This is what [`encode`]/[`decode`] use.
+    Plus,
+}
+
+/// Iterator that yields the percent-encoded bytes of `src` one at a time,
+/// without allocating. This is the core encode loop: [`encode`],
+/// [`encode_with`] and [`encode_into`] are all thin wrappers that collect it
+/// into a sink; reach for this directly only if you need the output
+/// incrementally.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{Bitmask, SpaceMode, EncodeIter};
+/// let encoded: String = EncodeIter::new(b"a b", Bitmask::RFC3986, SpaceMode::Percent)
+///     .map(char::from)
+///     .collect();
+/// assert_eq!(&encoded, "a%20b");
+/// ```
+pub struct EncodeIter<'a> {
+    src: core::slice::Iter<'a, u8>,
+    mask: Bitmask,
+    space: SpaceMode,
+    pending: [u8; 2],
+    pending_len: u8,
+}
+
+impl<'a> EncodeIter<'a> {
+    /// Creates an iterator that encodes `src` under `mask`/`space`
+    pub fn new(src: &'a [u8], mask: Bitmask, space: SpaceMode) -> Self {
+        EncodeIter { src: src.iter(), mask, space, pending: [0; 2], pending_len: 0 }
+    }
+}
+
+impl Iterator for EncodeIter<'_> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        if self.pending_len > 0 {
+            let idx = self.pending.len() - self.pending_len as usize;
+            self.pending_len -= 1;
+            return Some(self.pending[idx]);
+        }
+        let ch = *self.src.next()?;
+        if ch == b' ' && self.space == SpaceMode::Plus {
+            Some(b'+')
+        } else if self.mask.contains(ch) {
+            Some(ch)
+        } else {
+            self.pending = [HEX[ch as usize >> 4], HEX[ch as usize & 0xF]];
+            self.pending_len = 2;
+            Some(b'%')
+        }
+    }
+}
+
+/// Encodes `src` into `out` using the provided [`Bitmask`] and [`SpaceMode`],
+/// without allocating a buffer of its own. Works in `no_std` contexts
+/// without the `alloc` feature, as long as `out` does.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{Bitmask, SpaceMode, encode_into};
+/// use core::fmt::Write;
+/// let mut out = String::new();
+/// encode_into(b"a b", Bitmask::RFC3986, SpaceMode::Percent, &mut out).unwrap();
+/// assert_eq!(&out, "a%20b");
+/// ```
+pub fn encode_into(
+    src: &[u8],
+    mask: Bitmask,
+    space: SpaceMode,
+    out: &mut impl core::fmt::Write,
+) -> core::fmt::Result {
+    for b in EncodeIter::new(src, mask, space) {
+        out.write_char(b as char)?;
+    }
+    Ok(())
+}
+
+/// Encodes given slice using the provided [`Bitmask`] and [`SpaceMode`]
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{Bitmask, SpaceMode, encode_with};
+/// let encoded = encode_with(b"a b+c", Bitmask::RFC3986, SpaceMode::Percent);
+/// assert_eq!(&encoded, "a%20b+c");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn encode_with(src: &[u8], mask: Bitmask, space: SpaceMode) -> String {
+    let mut out = String::with_capacity(src.len());
+    encode_into(src, mask, space, &mut out).expect("writing to a String never fails");
+    out
+}
+
+/// Encodes given slice using provided [`Bitmask`], mapping space to `+`
+/// (`application/x-www-form-urlencoded` semantics). For RFC 3986 semantics,
+/// where space becomes `%20` and `+` stays a literal `+`, use
+/// [`encode_with`] with [`SpaceMode::Percent`].
 /// # Example
 /// ```
 /// # use percent_encoding_lite::Bitmask;
@@ -69,53 +190,205 @@ const HEX: &[u8] = b"0123456789ABCDEF";
 /// let encoded = percent_encoding_lite::encode(string.as_bytes(), Bitmask::URI);
 /// assert_eq!(&encoded, "Dragonborn,+dragonborn,+by+his+honor+is+sworn");
 /// ```
+#[cfg(feature = "alloc")]
 pub fn encode(src: &[u8], mask: Bitmask) -> String {
-    let mut out = String::with_capacity(src.len());
-    for ch in src.iter().copied() {
-        if ch == b' ' {
-            out.push('+');
-        } else if mask.contains(ch) {
-            out.push(ch as char);
-        } else {
-            out.push('%');
-            out.push(HEX[ch as usize >> 4] as char);
-            out.push(HEX[ch as usize & 0xF] as char);
+    encode_with(src, mask, SpaceMode::Plus)
+}
+
+/// Iterator that yields the decoded bytes of `src` one at a time, without
+/// allocating. This is the core decode loop: [`decode`], [`decode_with`]
+/// and [`decode_into`] are all thin wrappers that collect it into a sink;
+/// reach for this directly only if you need the output incrementally.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{SpaceMode, DecodeIter};
+/// let decoded: Vec<u8> = DecodeIter::new("a%20b+c", SpaceMode::Percent).collect();
+/// assert_eq!(&decoded, b"a b+c");
+/// ```
+pub struct DecodeIter<'a> {
+    slice: &'a [u8],
+    space: SpaceMode,
+}
+
+impl<'a> DecodeIter<'a> {
+    /// Creates an iterator that decodes `src` under `space`
+    pub fn new(src: &'a str, space: SpaceMode) -> Self {
+        DecodeIter { slice: src.as_bytes(), space }
+    }
+}
+
+impl Iterator for DecodeIter<'_> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        let &i = self.slice.first()?;
+        self.slice = &self.slice[1..];
+
+        if i == b'+' && self.space == SpaceMode::Plus {
+            return Some(b' ');
+        }
+        if i != b'%' {
+            return Some(i);
+        }
+        if self.slice.len() < 2 {
+            return Some(i);
+        }
+        let (hi, lo) = (self.slice[0], self.slice[1]);
+        match char::from(hi).to_digit(16).zip(char::from(lo).to_digit(16)) {
+            Some((hi, lo)) => {
+                self.slice = &self.slice[2..];
+                Some((hi * 16 + lo) as u8)
+            }
+            None => Some(i),
         }
     }
+}
+
+/// Decodes `src` into `out` using the provided [`SpaceMode`], without
+/// allocating a buffer of its own. Works in `no_std` contexts without the
+/// `alloc` feature, as long as `out` does.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{SpaceMode, decode_into};
+/// let mut out = Vec::new();
+/// decode_into("a%20b+c", SpaceMode::Percent, &mut out);
+/// assert_eq!(&out, b"a b+c");
+/// ```
+pub fn decode_into(src: &str, space: SpaceMode, out: &mut impl Extend<u8>) {
+    out.extend(DecodeIter::new(src, space));
+}
+
+/// Decodes a percent encoded string using the provided [`SpaceMode`]
+/// # Example
+/// ```
+/// # use percent_encoding_lite::{SpaceMode, decode_with};
+/// assert_eq!(decode_with("a%20b+c", SpaceMode::Percent), b"a b+c");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_with(src: &str, space: SpaceMode) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len());
+    decode_into(src, space, &mut out);
     out
 }
 
-/// Decodes a percent encoded string
+/// Decodes a percent encoded string, mapping `+` to space
+/// (`application/x-www-form-urlencoded` semantics). For RFC 3986 semantics,
+/// where `+` is left as a literal `+`, use [`decode_with`] with
+/// [`SpaceMode::Percent`].
 /// # Example
 /// ```
 /// let encoded = "%54%6F%20%6B%65%65%70%20%65%76%69%6C%20%66%6F%72%65%76%65%72%20%61%74%20%62%61%79%21";
 /// let decoded = percent_encoding_lite::decode(encoded);
 /// assert_eq!(&decoded, b"To keep evil forever at bay!");
 /// ```
+#[cfg(feature = "alloc")]
 pub fn decode(src: &str) -> Vec<u8> {
-    let mut slice = src.as_bytes();
-    let mut out = vec![];
-    while let Some(&i) = slice.first() {
-        slice = &slice[1..]; // I wish rust had random access iterators
+    decode_with(src, SpaceMode::Plus)
+}
+
+/// Encodes `src` like [`encode`], but borrows `src` unchanged instead of
+/// allocating when no byte actually needs escaping.
+/// # Example
+/// ```
+/// # use std::borrow::Cow;
+/// # use percent_encoding_lite::{Bitmask, encode_cow};
+/// assert_eq!(encode_cow(b"already-safe", Bitmask::URI_COMPONENT), Cow::Borrowed("already-safe"));
+/// assert_eq!(encode_cow(b"a b", Bitmask::URI_COMPONENT), Cow::Owned::<str>("a+b".to_string()));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn encode_cow(src: &[u8], mask: Bitmask) -> Cow<'_, str> {
+    if src.iter().all(|&ch| ch != b' ' && mask.contains(ch)) {
+        // every byte that passed the check above is in 32..=127, i.e. ASCII
+        Cow::Borrowed(core::str::from_utf8(src).unwrap())
+    } else {
+        Cow::Owned(encode(src, mask))
+    }
+}
+
+/// Decodes `src` like [`decode`], but borrows `src`'s bytes unchanged
+/// instead of allocating when there is nothing to decode.
+/// # Example
+/// ```
+/// # use std::borrow::Cow;
+/// # use percent_encoding_lite::decode_cow;
+/// assert_eq!(decode_cow("already-safe"), Cow::Borrowed(b"already-safe"));
+/// assert_eq!(decode_cow("a+b"), Cow::Owned::<[u8]>(b"a b".to_vec()));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_cow(src: &str) -> Cow<'_, [u8]> {
+    if !src.bytes().any(|b| b == b'%' || b == b'+') {
+        Cow::Borrowed(src.as_bytes())
+    } else {
+        Cow::Owned(decode(src))
+    }
+}
 
-        if i == b'+' {
+/// What specifically was wrong with a percent escape rejected by [`decode_strict`]
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// Fewer than two bytes followed the `%`
+    IncompleteEscape,
+    /// One of the two bytes following `%` was not a hex digit
+    InvalidHexDigit {
+        /// The offending byte
+        found: u8,
+    },
+}
+
+/// Error returned by [`decode_strict`] for a malformed percent escape
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Byte offset of the `%` that starts the malformed escape
+    pub offset: usize,
+    /// What specifically was wrong with it
+    pub kind: DecodeErrorKind,
+}
+
+/// Decodes a percent encoded string, rejecting malformed escapes instead of
+/// passing them through literally the way [`decode`] does. Use this for
+/// untrusted input where a stray `%` should be an error, not a character.
+/// # Example
+/// ```
+/// use percent_encoding_lite::{decode_strict, DecodeErrorKind};
+/// assert_eq!(decode_strict("%41%42").unwrap(), b"AB");
+///
+/// let err = decode_strict("%ZZ").unwrap_err();
+/// assert_eq!(err.offset, 0);
+/// assert_eq!(err.kind, DecodeErrorKind::InvalidHexDigit { found: b'Z' });
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_strict(src: &str) -> Result<Vec<u8>, DecodeError> {
+    let bytes = src.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'+' {
             out.push(b' ');
-        } else if i != b'%' {
-            out.push(i);
+            i += 1;
+        } else if b != b'%' {
+            out.push(b);
+            i += 1;
         } else {
-            if slice.len() < 2 { out.push(i); slice = &slice[1..]; continue; }
-            let (hi, lo) = (slice[0], slice[1]);
-            let digits = char::from(hi).to_digit(16).zip(char::from(lo).to_digit(16));
-            if digits.is_none() { out.push(i); slice = &slice[1..]; continue; }
-            let (hi, lo) = digits.unwrap();
-            out.push((hi * 16 + lo) as u8);
-            slice = &slice[2..];
+            if i + 3 > bytes.len() {
+                return Err(DecodeError { offset: i, kind: DecodeErrorKind::IncompleteEscape });
+            }
+            let (hi, lo) = (bytes[i + 1], bytes[i + 2]);
+            let hi_digit = char::from(hi)
+                .to_digit(16)
+                .ok_or(DecodeError { offset: i, kind: DecodeErrorKind::InvalidHexDigit { found: hi } })?;
+            let lo_digit = char::from(lo)
+                .to_digit(16)
+                .ok_or(DecodeError { offset: i, kind: DecodeErrorKind::InvalidHexDigit { found: lo } })?;
+            out.push((hi_digit * 16 + lo_digit) as u8);
+            i += 3;
         }
     }
-    out
+    Ok(out)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod test {
     use super::*;
     #[test]
@@ -131,6 +404,52 @@ mod test {
         assert_eq!(&decoded, correct);
     }
     #[test]
+    fn urldecode_strict_test() {
+        let encoded = "Anno%201404";
+        let decoded = String::from_utf8(decode_strict(encoded).unwrap()).unwrap();
+        assert_eq!(&decoded, "Anno 1404");
+
+        let err = decode_strict("100%").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.kind, DecodeErrorKind::IncompleteEscape);
+
+        let err = decode_strict("100%A").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.kind, DecodeErrorKind::IncompleteEscape);
+
+        let err = decode_strict("100%ZZ").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.kind, DecodeErrorKind::InvalidHexDigit { found: b'Z' });
+    }
+    #[test]
+    fn space_mode_test() {
+        let encoded = encode_with(b"a b+c", Bitmask::RFC3986, SpaceMode::Percent);
+        assert_eq!(&encoded, "a%20b+c");
+        assert_eq!(decode_with(&encoded, SpaceMode::Percent), b"a b+c");
+
+        let encoded = encode_with(b"a b+c", Bitmask::RFC3986, SpaceMode::Plus);
+        assert_eq!(&encoded, "a+b+c");
+        assert_eq!(decode_with(&encoded, SpaceMode::Plus), b"a b c");
+    }
+    #[test]
+    fn iter_test() {
+        let encoded: String = EncodeIter::new(b"a b\"c", Bitmask::RFC3986, SpaceMode::Percent)
+            .map(char::from)
+            .collect();
+        assert_eq!(&encoded, "a%20b%22c");
+
+        let decoded: Vec<u8> = DecodeIter::new("a%20b%22c", SpaceMode::Percent).collect();
+        assert_eq!(&decoded, b"a b\"c");
+    }
+    #[test]
+    fn cow_test() {
+        assert!(matches!(encode_cow(b"already-safe", Bitmask::URI_COMPONENT), Cow::Borrowed(_)));
+        assert!(matches!(encode_cow(b"a b", Bitmask::URI_COMPONENT), Cow::Owned(_)));
+
+        assert!(matches!(decode_cow("already-safe"), Cow::Borrowed(_)));
+        assert!(matches!(decode_cow("a%20b"), Cow::Owned(_)));
+    }
+    #[test]
     fn urlencode_test() {
         let orig = "Microsoft Windows 10, version 22H2, build 19045.2846 (updated April 2023) - Оригинальные образы от Microsoft MSDN [Ru]";
         let encoded = encode(orig.as_bytes(), Bitmask::URI_COMPONENT);
@@ -0,0 +1,256 @@
+//! Punycode / IDNA encoding for internationalized hostnames
+//!
+//! Percent-encoding only covers bytes; a host label containing non-ASCII
+//! code points must instead be transformed into its ASCII-Compatible
+//! Encoding (ACE) form using the Bootstring algorithm from RFC 3492.
+//! Check [`punycode_encode`], [`punycode_decode`] and [`idna_to_ascii`]
+//! for example usage.
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+#[cfg(feature = "alloc")]
+const BASE: u32 = 36;
+#[cfg(feature = "alloc")]
+const TMIN: u32 = 1;
+#[cfg(feature = "alloc")]
+const TMAX: u32 = 26;
+#[cfg(feature = "alloc")]
+const SKEW: u32 = 38;
+#[cfg(feature = "alloc")]
+const DAMP: u32 = 700;
+#[cfg(feature = "alloc")]
+const INITIAL_BIAS: u32 = 72;
+#[cfg(feature = "alloc")]
+const INITIAL_N: u32 = 128;
+
+#[cfg(feature = "alloc")]
+fn adapt(delta: u32, numpoints: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / numpoints;
+    let mut k = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+    k + ((BASE - TMIN + 1) * delta) / (delta + SKEW)
+}
+
+#[cfg(feature = "alloc")]
+fn encode_digit(d: u32) -> u8 {
+    if d < 26 { b'a' + d as u8 } else { b'0' + (d - 26) as u8 }
+}
+
+#[cfg(feature = "alloc")]
+fn decode_digit(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32),
+        '0'..='9' => Some(c as u32 - '0' as u32 + 26),
+        _ => None,
+    }
+}
+
+/// Encodes a single label using the Bootstring algorithm (RFC 3492).
+///
+/// Returns `None` on arithmetic overflow, which only happens for
+/// pathologically long or high-codepoint inputs.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::punycode::punycode_encode;
+/// assert_eq!(punycode_encode("münchen").as_deref(), Some("mnchen-3ya"));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn punycode_encode(src: &str) -> Option<String> {
+    let input: Vec<u32> = src.chars().map(|c| c as u32).collect();
+    let total = input.len() as u32;
+
+    let mut out: String = input
+        .iter()
+        .copied()
+        .filter(|&c| c < 128)
+        .map(|c| c as u8 as char)
+        .collect();
+    let b = out.len() as u32;
+    if b > 0 {
+        out.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut h = b;
+
+    while h < total {
+        let m = input.iter().copied().filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(h + 1)?)?;
+        n = m;
+
+        for &c in &input {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        TMIN
+                    } else if k >= bias + TMAX {
+                        TMAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        out.push(encode_digit(q) as char);
+                        break;
+                    }
+                    out.push(encode_digit(t + (q - t) % (BASE - t)) as char);
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                bias = adapt(delta, h + 1, h == b);
+                delta = 0;
+                h += 1;
+            }
+        }
+        delta = delta.checked_add(1)?;
+        n = n.checked_add(1)?;
+    }
+
+    Some(out)
+}
+
+/// Decodes a single Bootstring-encoded label (RFC 3492).
+///
+/// Returns `None` for malformed input: an uppercase digit, an unknown
+/// character, a truncated variable-length integer, or overflow.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::punycode::punycode_decode;
+/// assert_eq!(punycode_decode("mnchen-3ya").as_deref(), Some("münchen"));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn punycode_decode(src: &str) -> Option<String> {
+    let (basic, rest) = match src.rfind('-') {
+        Some(i) => (&src[..i], &src[i + 1..]),
+        None => ("", src),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut out: Vec<char> = basic.chars().collect();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+
+    let mut chars = rest.chars();
+    while let Some(first) = chars.clone().next() {
+        let _ = first;
+        let oldi = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let c = chars.next()?;
+            let digit = decode_digit(c)?;
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = if k <= bias {
+                TMIN
+            } else if k >= bias + TMAX {
+                TMAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+
+        let out_len = out.len() as u32 + 1;
+        bias = adapt(i - oldi, out_len, oldi == 0);
+        n = n.checked_add(i / out_len)?;
+        i %= out_len;
+        out.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+
+    Some(out.into_iter().collect())
+}
+
+/// Converts a (possibly internationalized) hostname into its all-ASCII
+/// IDNA form, punycode-encoding any label that isn't already ASCII and
+/// prefixing it with `xn--`. Labels that are already ASCII are left
+/// untouched; a label that fails to encode is passed through as-is.
+/// # Example
+/// ```
+/// # use percent_encoding_lite::punycode::idna_to_ascii;
+/// assert_eq!(idna_to_ascii("münchen.de"), "xn--mnchen-3ya.de");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn idna_to_ascii(host: &str) -> String {
+    host.split('.')
+        .map(|label| {
+            if label.is_ascii() {
+                label.to_string()
+            } else {
+                match punycode_encode(label) {
+                    Some(encoded) => format!("xn--{encoded}"),
+                    None => label.to_string(),
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_test() {
+        assert_eq!(punycode_encode("münchen").as_deref(), Some("mnchen-3ya"));
+        assert_eq!(punycode_decode("mnchen-3ya").as_deref(), Some("münchen"));
+        assert_eq!(idna_to_ascii("münchen.de"), "xn--mnchen-3ya.de");
+    }
+
+    #[test]
+    fn zero_basic_chars_label() {
+        // An all-non-ASCII label has no basic chars, so the encoding has no
+        // leading `-` delimiter.
+        let encoded = punycode_encode("ü").unwrap();
+        assert!(!encoded.starts_with('-'));
+        assert_eq!(punycode_decode(&encoded).as_deref(), Some("ü"));
+    }
+
+    #[test]
+    fn decode_rejects_uppercase_digits() {
+        // RFC 3492 digits are lowercase `a`-`z`/`0`-`9`; uppercase must be rejected, not
+        // silently folded.
+        assert_eq!(punycode_decode("mnchen-3YA"), None);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_extension() {
+        // "mnchen-3ya" is the full encoding of "münchen"; dropping the last digit
+        // leaves a variable-length integer with no terminating digit.
+        assert_eq!(punycode_decode("mnchen-3y"), None);
+    }
+
+    #[test]
+    fn decode_rejects_non_ascii_basic_part() {
+        // The part before the last `-` is supposed to be the copied basic (ASCII)
+        // characters; anything else means the input wasn't produced by `punycode_encode`.
+        assert_eq!(punycode_decode("mü-3ya"), None);
+    }
+
+    #[test]
+    fn decode_rejects_overflow() {
+        // A long run of maximal digits keeps requesting more digits of the
+        // variable-length integer forever, which must fail via the overflow
+        // guard rather than panic or loop forever.
+        assert_eq!(punycode_decode("999999999999"), None);
+    }
+}